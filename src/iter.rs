@@ -1,7 +1,8 @@
+use std::mem;
 use std::slice;
 use std::iter::{Map, FilterMap, FromIterator};
 
-use {Trie, TrieKey};
+use {Trie, TrieNode, TrieKey};
 
 // MY EYES.
 pub type Child<K, V> = Box<Trie<K, V>>;
@@ -139,3 +140,49 @@ impl<K, V> FromIterator<(K, V)> for Trie<K, V> where K: TrieKey {
         trie
     }
 }
+
+/// Iterator over the owned keys and values of a `Trie`, taking them out of each node as it
+/// walks the tree rather than cloning.
+pub struct IntoIter<K, V> {
+    stack: Vec<TrieNode<K, V>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while let Some(mut node) = self.stack.pop() {
+            // Push in reverse slot order so popping restores ascending order, matching `Iter`.
+            for child in node.children.iter_mut().rev() {
+                if let Some(child) = child.take() {
+                    self.stack.push(*child);
+                }
+            }
+            if let Some(kv) = node.key_value.take() {
+                return Some((kv.key, kv.value));
+            }
+        }
+        None
+    }
+}
+
+impl<K, V> IntoIterator for Trie<K, V> where K: TrieKey {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter { stack: vec![self.node] }
+    }
+}
+
+impl<K, V> Trie<K, V> where K: TrieKey {
+    /// Remove and return all of this trie's entries, resetting it to empty.
+    ///
+    /// Unlike `IntoIterator::into_iter`, this doesn't consume the `Trie` itself, leaving it
+    /// available (now empty) for reuse.
+    pub fn drain(&mut self) -> IntoIter<K, V> {
+        let root = mem::replace(&mut self.node, TrieNode::new());
+        self.length = 0;
+        IntoIter { stack: vec![root] }
+    }
+}
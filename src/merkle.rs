@@ -0,0 +1,234 @@
+//! Optional Merkle hashing and inclusion proofs over a `Trie`.
+//!
+//! Every `TrieNode` is assigned a 32-byte digest computed from its fragment, its value
+//! (if any), and the digests of its children in canonical child-slot order. Folding those
+//! digests up to the root gives a `Trie` a stable `root_hash` that depends only on the set
+//! of key-value pairs stored, not on insertion order, which makes it suitable for
+//! content-addressed or authenticated storage.
+//!
+//! The hash function itself is left pluggable via `MerkleHasher` so callers can use
+//! whichever 32-byte digest they trust (SHA-256, BLAKE2s, ...); this module only fixes the
+//! shape of what gets hashed.
+
+use std::borrow::Borrow;
+
+use {Trie, TrieNode, TrieKey};
+
+const BRANCH_FACTOR: usize = 16;
+
+/// A pluggable 32-byte hash function used to build the Merkle tree.
+///
+/// Implement this for whichever digest you want `root_hash`, `prove` and `verify` to use.
+pub trait MerkleHasher {
+    /// Hash an arbitrary byte slice down to 32 bytes.
+    fn hash(data: &[u8]) -> [u8; 32];
+}
+
+/// One level of a `MerkleProof`, from the value's node up towards the root.
+#[derive(Clone, Debug)]
+pub struct ProofStep {
+    /// The fragment of the node this step was produced from.
+    fragment: Vec<u8>,
+    /// The value stored at this node, if any.
+    value: Option<Vec<u8>>,
+    /// Digests of this node's children, keyed by slot, excluding the slot that continues
+    /// down towards the key being proven (that digest is supplied by the previous step).
+    sibling_digests: Vec<(usize, [u8; 32])>,
+    /// The slot of the child that continues down towards the key, or `None` for the
+    /// bottom-most step (the node that actually stores the value).
+    path_slot: Option<usize>,
+}
+
+/// An inclusion proof that a key-value pair is present under a given root hash.
+#[derive(Clone, Debug)]
+pub struct MerkleProof {
+    steps: Vec<ProofStep>,
+}
+
+fn fragment_bytes(fragment: &::NibbleVec) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(fragment.len());
+    for i in 0..fragment.len() {
+        bytes.push(fragment.get(i));
+    }
+    bytes
+}
+
+/// Append `data` to `buf` preceded by its length, so that concatenating two differently
+/// sized fields can never collide with a third split at a different boundary.
+fn push_len_prefixed(buf: &mut Vec<u8>, data: &[u8]) {
+    let len = data.len() as u32;
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(data);
+}
+
+fn hash_node<H: MerkleHasher, K, V>(node: &TrieNode<K, V>) -> [u8; 32]
+    where V: AsRef<[u8]>
+{
+    let mut buf = Vec::new();
+    push_len_prefixed(&mut buf, &fragment_bytes(&node.key));
+    match node.key_value {
+        Some(ref kv) => {
+            buf.push(1);
+            push_len_prefixed(&mut buf, kv.value.as_ref());
+        }
+        None => buf.push(0),
+    }
+    for child in node.children.iter() {
+        match *child {
+            Some(ref child) => buf.extend_from_slice(&hash_node::<H, K, V>(child)),
+            None => buf.extend_from_slice(&[0u8; 32]),
+        }
+    }
+    H::hash(&buf)
+}
+
+fn child_digests<H: MerkleHasher, K, V>(node: &TrieNode<K, V>) -> Vec<(usize, [u8; 32])>
+    where V: AsRef<[u8]>
+{
+    node.children.iter().enumerate()
+        .filter_map(|(i, child)| child.as_ref().map(|child| (i, hash_node::<H, K, V>(child))))
+        .collect()
+}
+
+impl<K, V> Trie<K, V> where K: TrieKey {
+    /// Compute the root hash of this trie using hasher `H`.
+    ///
+    /// The same set of key-value pairs always yields the same root hash regardless of
+    /// insertion order, since children are folded in canonical child-slot order.
+    pub fn root_hash<H: MerkleHasher>(&self) -> [u8; 32] where V: AsRef<[u8]> {
+        hash_node::<H, K, V>(&self.node)
+    }
+
+    /// Build an inclusion proof that `key` maps to its currently stored value.
+    ///
+    /// Returns `None` if `key` is not present.
+    pub fn prove<H: MerkleHasher, Q: ?Sized>(&self, key: &Q) -> Option<MerkleProof>
+        where K: Borrow<Q>, Q: TrieKey, V: AsRef<[u8]>
+    {
+        let mut key_fragments = ::NibbleVec::from_byte_vec(key.encode());
+        let mut path = vec![];
+        let mut current = &self.node;
+
+        while key_fragments.len() > 0 {
+            let bucket = key_fragments.get(0) as usize;
+            match current.children[bucket] {
+                Some(ref child) => {
+                    let common = key_fragments.common_prefix_len(&child.key);
+                    if common != child.key.len() {
+                        return None;
+                    }
+                    path.push((current, bucket));
+                    key_fragments = key_fragments.split(common);
+                    current = child;
+                }
+                None => return None,
+            }
+        }
+
+        if current.key_value.is_none() {
+            return None;
+        }
+
+        let mut steps = Vec::with_capacity(path.len() + 1);
+
+        steps.push(ProofStep {
+            fragment: fragment_bytes(&current.key),
+            value: current.key_value.as_ref().map(|kv| kv.value.as_ref().to_vec()),
+            sibling_digests: child_digests::<H, K, V>(current),
+            path_slot: None,
+        });
+
+        for (node, slot) in path.into_iter().rev() {
+            let mut siblings = child_digests::<H, K, V>(node);
+            siblings.retain(|&(i, _)| i != slot);
+            steps.push(ProofStep {
+                fragment: fragment_bytes(&node.key),
+                value: node.key_value.as_ref().map(|kv| kv.value.as_ref().to_vec()),
+                sibling_digests: siblings,
+                path_slot: Some(slot),
+            });
+        }
+
+        Some(MerkleProof { steps })
+    }
+}
+
+/// Verify that `key` maps to `value` under `root_hash`, given `proof`.
+///
+/// Recomputes the value node's digest and folds each proof step upward, combining the
+/// step's fragment, value and sibling digests with the accumulated digest from below, until
+/// the final fold must equal `root_hash`.
+pub fn verify<H: MerkleHasher, K, V>(root_hash: [u8; 32], key: &K, value: &V, proof: &MerkleProof)
+    -> bool
+    where K: TrieKey, V: AsRef<[u8]>
+{
+    let bottom = match proof.steps.first() {
+        Some(step) => step,
+        None => return false,
+    };
+    if bottom.value.as_ref().map(|v| v.as_slice()) != Some(value.as_ref()) {
+        return false;
+    }
+
+    // Re-derive `key`'s nibble path and check it agrees with the path recorded in the
+    // proof at every level (root to leaf), so a proof built for one key can't be replayed
+    // to falsely certify a different key.
+    let mut remaining = ::NibbleVec::from_byte_vec(key.encode());
+    for step in proof.steps.iter().rev() {
+        if step.fragment.len() > remaining.len() {
+            return false;
+        }
+        for (i, &nibble) in step.fragment.iter().enumerate() {
+            if remaining.get(i) != nibble {
+                return false;
+            }
+        }
+        remaining = remaining.split(step.fragment.len());
+
+        match step.path_slot {
+            Some(slot) => {
+                // The slot nibble is also the first nibble of the next step's fragment,
+                // which consumes it.
+                if remaining.len() == 0 || remaining.get(0) as usize != slot {
+                    return false;
+                }
+            }
+            None => {
+                if remaining.len() != 0 {
+                    return false;
+                }
+            }
+        }
+    }
+
+    let mut acc: Option<[u8; 32]> = None;
+    for step in &proof.steps {
+        let mut buf = Vec::new();
+        push_len_prefixed(&mut buf, &step.fragment);
+        match step.value {
+            Some(ref v) => {
+                buf.push(1);
+                push_len_prefixed(&mut buf, v);
+            }
+            None => buf.push(0),
+        }
+
+        let mut slots = [[0u8; 32]; BRANCH_FACTOR];
+        for &(i, digest) in &step.sibling_digests {
+            slots[i] = digest;
+        }
+        if let Some(slot) = step.path_slot {
+            match acc {
+                Some(digest) => slots[slot] = digest,
+                None => return false,
+            }
+        }
+        for digest in slots.iter() {
+            buf.extend_from_slice(digest);
+        }
+
+        acc = Some(H::hash(&buf));
+    }
+
+    acc == Some(root_hash)
+}
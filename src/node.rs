@@ -0,0 +1,116 @@
+//! Node-level fallible insertion.
+//!
+//! This is the node-splitting counterpart to `Trie::try_insert`: where the ordinary
+//! `insert` path grows fragment storage and allocates new nodes unconditionally, these
+//! methods reserve that storage up front and bail out with the underlying
+//! `TryReserveError` instead of letting an allocation failure unwind through a panic or
+//! abort, leaving the node exactly as it was found on `Err`.
+//!
+//! Box allocation for the (small, fixed-size) node and key-value shells themselves still
+//! goes through ordinary `Box::new`, since stable Rust has no fallible box allocation; the
+//! `try_reserve_exact` calls below cover the large, variable-sized growth (fragment
+//! storage, which scales with key length) that accounts for the overwhelming majority of
+//! realistic allocation failures in this structure.
+
+use std::collections::TryReserveError;
+use std::mem;
+
+use {TrieNode, TrieKey, NibbleVec, KeyValue};
+
+/// Copy the nibbles of `nv` in `[start, end)` into a freshly, fallibly allocated `NibbleVec`.
+fn try_nibble_slice(nv: &NibbleVec, start: usize, end: usize) -> Result<NibbleVec, TryReserveError> {
+    let len = end - start;
+    let mut bytes: Vec<u8> = Vec::new();
+    bytes.try_reserve_exact((len + 1) / 2)?;
+    // Pack two nibbles per byte, since `from_byte_vec` splits each byte into two nibbles.
+    for i in (start..end).step_by(2) {
+        let low = if i + 1 < end { nv.get(i + 1) } else { 0 };
+        bytes.push((nv.get(i) << 4) | low);
+    }
+    let mut nibbles = NibbleVec::from_byte_vec(bytes);
+    if len % 2 == 1 {
+        // Drop the padding nibble that filled out the last byte.
+        nibbles.split(len);
+    }
+    Ok(nibbles)
+}
+
+impl<K, V> TrieNode<K, V> where K: TrieKey {
+    /// Fallible counterpart to the ordinary node-level `insert`.
+    ///
+    /// `key_fragments` is the full byte-encoding of `key`, as produced by `key.encode()` and
+    /// passed down unchanged from `Trie::try_insert`.
+    pub fn try_insert(&mut self, key: K, value: V, key_fragments: Vec<u8>)
+        -> Result<Option<V>, TryReserveError>
+    {
+        let key_fragments = NibbleVec::from_byte_vec(key_fragments);
+        self.try_insert_nibbles(key, value, key_fragments)
+    }
+
+    fn try_insert_nibbles(&mut self, key: K, value: V, key_fragments: NibbleVec)
+        -> Result<Option<V>, TryReserveError>
+    {
+        let common = key_fragments.common_prefix_len(&self.key);
+        if common < self.key.len() {
+            // Only reachable if a caller mis-routes; every recursive call below only
+            // descends after confirming a full match against the child's own fragment.
+            return Ok(None);
+        }
+
+        let remaining = try_nibble_slice(&key_fragments, common, key_fragments.len())?;
+
+        if remaining.len() == 0 {
+            let kv = Box::new(KeyValue { key: key, value: value });
+            return Ok(mem::replace(&mut self.key_value, Some(kv)).map(|kv| kv.value));
+        }
+
+        let bucket = remaining.get(0) as usize;
+        match self.children[bucket] {
+            Some(ref mut child) => {
+                let child_common = remaining.common_prefix_len(&child.key);
+
+                if child_common == child.key.len() {
+                    // The whole of the child's fragment matches: recurse into it, passing
+                    // the key from the start of the child's fragment, which it strips itself.
+                    return child.try_insert_nibbles(key, value, remaining);
+                }
+
+                // Partial match: split `child`'s fragment, inserting a new intermediate
+                // node that holds the shared prefix, with the old child and the new entry
+                // as its two children (or the new entry living directly on the
+                // intermediate, if the key ends exactly at the split point).
+                let shared = try_nibble_slice(&child.key, 0, child_common)?;
+                let child_rest = try_nibble_slice(&child.key, child_common, child.key.len())?;
+                let new_rest = try_nibble_slice(&remaining, child_common, remaining.len())?;
+
+                let mut old_child = mem::replace(child, Box::new(TrieNode::new()));
+                old_child.key = child_rest;
+                let old_bucket = old_child.key.get(0) as usize;
+
+                let mut intermediate = TrieNode::new();
+                intermediate.key = shared;
+
+                if new_rest.len() == 0 {
+                    intermediate.key_value = Some(Box::new(KeyValue { key: key, value: value }));
+                } else {
+                    let new_bucket = new_rest.get(0) as usize;
+                    let mut leaf = TrieNode::new();
+                    leaf.key = new_rest;
+                    leaf.key_value = Some(Box::new(KeyValue { key: key, value: value }));
+                    intermediate.children[new_bucket] = Some(Box::new(leaf));
+                }
+                intermediate.children[old_bucket] = Some(old_child);
+
+                *child = Box::new(intermediate);
+                Ok(None)
+            }
+            None => {
+                let mut leaf = TrieNode::new();
+                leaf.key = remaining;
+                leaf.key_value = Some(Box::new(KeyValue { key: key, value: value }));
+                self.children[bucket] = Some(Box::new(leaf));
+                Ok(None)
+            }
+        }
+    }
+}
@@ -3,9 +3,13 @@
 use {Trie, TrieKey};
 use std::iter::FromIterator;
 use std::collections::{HashSet, HashMap};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use quickcheck::{quickcheck, Gen, Arbitrary};
 use rand::Rng;
 
+use merkle::{self, MerkleHasher};
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Key(Vec<u8>);
 
@@ -138,6 +142,35 @@ fn length_trie(keys: HashSet<Key>) -> Trie<Key, usize> {
     t
 }
 
+// Construct a trie from a set of keys, with each key mapped to its own byte encoding, for
+// use with the `merkle` module, which needs `V: AsRef<[u8]>`.
+fn byte_value_trie(keys: HashSet<Key>) -> Trie<Key, Vec<u8>> {
+    let mut t = Trie::new();
+    for k in keys {
+        let v = k.0.clone();
+        t.insert(k, v);
+    }
+    t
+}
+
+// A non-cryptographic stand-in `MerkleHasher` for exercising the Merkle hashing and proof
+// logic's own invariants, not the quality of any particular digest.
+struct TestHasher;
+
+impl MerkleHasher for TestHasher {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, chunk) in out.chunks_mut(8).enumerate() {
+            let mut hasher = DefaultHasher::new();
+            i.hash(&mut hasher);
+            data.hash(&mut hasher);
+            let bytes = hasher.finish().to_be_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        out
+    }
+}
+
 #[test]
 fn keys_iter() {
     fn prop(RandomKeys(keys): RandomKeys) -> bool {
@@ -168,3 +201,171 @@ fn values_iter() {
     }
     quickcheck(prop as fn(RandomKeys) -> bool);
 }
+
+#[test]
+fn find_prefixes_matches_all_stored_prefixes() {
+    fn prop(RandomKeys(keys): RandomKeys) -> bool {
+        let trie = length_trie(keys.clone());
+
+        for k in &keys {
+            let mut expected: Vec<&Key> = keys.iter()
+                .filter(|other| k.0.starts_with(other.0.as_slice()))
+                .collect();
+            expected.sort_by_key(|other| other.len());
+
+            let found = trie.find_prefixes(k);
+            if found.len() != expected.len() { return false }
+            for (&(found_key, found_value), expected_key) in found.iter().zip(expected.iter()) {
+                if found_key != *expected_key { return false }
+                if *found_value != expected_key.len() { return false }
+            }
+
+            match (trie.find_longest_prefix(k), expected.last()) {
+                (Some((found_key, found_value)), Some(expected_key)) => {
+                    if found_key != *expected_key || *found_value != expected_key.len() {
+                        return false;
+                    }
+                }
+                (None, None) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+    quickcheck(prop as fn(RandomKeys) -> bool);
+}
+
+#[test]
+fn get_descendant_yields_all_keys_with_prefix() {
+    fn prop(RandomKeys(keys): RandomKeys) -> bool {
+        let trie = length_trie(keys.clone());
+
+        for k in &keys {
+            for i in 0 .. k.len() + 1 {
+                let prefix = Key(k.0[..i].to_vec());
+                let expected: HashSet<Key> = keys.iter()
+                    .filter(|other| other.0.starts_with(prefix.0.as_slice()))
+                    .cloned()
+                    .collect();
+
+                match trie.get_descendant(&prefix) {
+                    Some(sub) => {
+                        let found: HashSet<Key> = sub.iter().map(|(k, _)| k.clone()).collect();
+                        if found != expected { return false }
+                    }
+                    None => if !expected.is_empty() { return false },
+                }
+            }
+        }
+
+        true
+    }
+    quickcheck(prop as fn(RandomKeys) -> bool);
+}
+
+#[test]
+fn drain_empties_trie_and_yields_all_pairs() {
+    fn prop(RandomKeys(keys): RandomKeys) -> bool {
+        let mut trie = length_trie(keys.clone());
+
+        let drained: HashSet<Key> = trie.drain().map(|(k, _)| k).collect();
+        if drained != keys { return false }
+
+        if trie.len() != 0 { return false }
+        if trie.iter().next().is_some() { return false }
+
+        true
+    }
+    quickcheck(prop as fn(RandomKeys) -> bool);
+}
+
+#[test]
+fn into_iter_yields_all_pairs_exactly_once() {
+    fn prop(RandomKeys(keys): RandomKeys) -> bool {
+        let trie = length_trie(keys.clone());
+
+        let mut seen = HashSet::new();
+        for (k, v) in trie {
+            if v != k.len() { return false }
+            if !seen.insert(k) { return false }
+        }
+
+        seen == keys
+    }
+    quickcheck(prop as fn(RandomKeys) -> bool);
+}
+
+#[test]
+fn try_insert_matches_insert_on_success() {
+    fn prop(RandomKeys(keys): RandomKeys) -> bool {
+        let mut expected = Trie::new();
+        let mut actual = Trie::new();
+
+        for k in &keys {
+            let via_insert = expected.insert(k.clone(), k.len());
+            let via_try_insert = match actual.try_insert(k.clone(), k.len()) {
+                Ok(result) => result,
+                Err(_) => return false,
+            };
+            if via_insert != via_try_insert { return false }
+        }
+
+        if actual.len() != expected.len() { return false }
+
+        for k in &keys {
+            if actual.get(k) != expected.get(k) { return false }
+        }
+
+        true
+    }
+    quickcheck(prop as fn(RandomKeys) -> bool);
+}
+
+#[test]
+fn merkle_root_hash_is_insertion_order_independent() {
+    fn prop(RandomKeys(keys): RandomKeys) -> bool {
+        let forward = byte_value_trie(keys.clone());
+
+        let mut backward = Trie::new();
+        let ordered: Vec<&Key> = keys.iter().collect();
+        for k in ordered.into_iter().rev() {
+            let v = k.0.clone();
+            backward.insert(k.clone(), v);
+        }
+
+        forward.root_hash::<TestHasher>() == backward.root_hash::<TestHasher>()
+    }
+    quickcheck(prop as fn(RandomKeys) -> bool);
+}
+
+#[test]
+fn merkle_prove_verify_round_trip_and_tamper_detection() {
+    fn prop(RandomKeys(keys): RandomKeys) -> bool {
+        let trie = byte_value_trie(keys.clone());
+        let root = trie.root_hash::<TestHasher>();
+
+        for k in &keys {
+            let value = k.0.clone();
+
+            let proof = match trie.prove::<TestHasher, Key>(k) {
+                Some(proof) => proof,
+                None => return false,
+            };
+
+            if !merkle::verify::<TestHasher, Key, Vec<u8>>(root, k, &value, &proof) {
+                return false;
+            }
+
+            // A tampered claimed value must not verify against the same proof.
+            let mut wrong_value = value.clone();
+            wrong_value.push(0xff);
+            if merkle::verify::<TestHasher, Key, Vec<u8>>(root, k, &wrong_value, &proof) {
+                return false;
+            }
+        }
+
+        true
+    }
+    quickcheck(prop as fn(RandomKeys) -> bool);
+}
@@ -0,0 +1,63 @@
+//! Optional [serde](https://serde.rs) support for `Trie`, gated behind the `serde` feature.
+//!
+//! A trie serializes as a sequence of its key-value pairs, streamed through the existing
+//! `Iter` rather than exposing any internal node layout, and deserializes by replaying that
+//! sequence through `FromIterator`, so all of the usual structural invariants hold on the
+//! reconstructed trie.
+//!
+//! `lib.rs` declares this module as `#[cfg(feature = "serde")] mod serde_impl;`; the
+//! `#![cfg(feature = "serde")]` below is belt-and-suspenders so the whole file still compiles
+//! away to nothing if it's ever pulled in unconditionally.
+#![cfg(feature = "serde")]
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::iter::FromIterator;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::ser::SerializeSeq;
+use serde::de::{Visitor, SeqAccess};
+
+use {Trie, TrieKey};
+
+impl<K, V> Serialize for Trie<K, V>
+    where K: TrieKey + Serialize, V: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for (k, v) in self.iter() {
+            seq.serialize_element(&(k, v))?;
+        }
+        seq.end()
+    }
+}
+
+struct TrieVisitor<K, V> {
+    marker: PhantomData<fn() -> Trie<K, V>>,
+}
+
+impl<'de, K, V> Visitor<'de> for TrieVisitor<K, V>
+    where K: TrieKey + Deserialize<'de>, V: Deserialize<'de>
+{
+    type Value = Trie<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of key-value pairs")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Trie<K, V>, A::Error> where A: SeqAccess<'de> {
+        let mut pairs = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some((k, v)) = seq.next_element::<(K, V)>()? {
+            pairs.push((k, v));
+        }
+        Ok(Trie::from_iter(pairs))
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for Trie<K, V>
+    where K: TrieKey + Deserialize<'de>, V: Deserialize<'de>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Trie<K, V>, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_seq(TrieVisitor { marker: PhantomData })
+    }
+}
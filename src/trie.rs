@@ -1,3 +1,6 @@
+use std::borrow::Borrow;
+use std::collections::TryReserveError;
+
 use {Trie, TrieNode, TrieKey, SubTrie, SubTrieMut, NibbleVec};
 
 impl<K, V> Trie<K, V> where K: TrieKey {
@@ -10,13 +13,17 @@ impl<K, V> Trie<K, V> where K: TrieKey {
     }
 
     /// Fetch a reference to the given key's corresponding value, if any.
-    pub fn get(&self, key: &K) -> Option<&V> {
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
+        where K: Borrow<Q>, Q: TrieKey
+    {
         let key_fragments = key.encode();
         self.node.get(&key_fragments).and_then(|t| t.value_checked(key))
     }
 
     /// Fetch a mutable reference to the given key's corresponding value, if any.
-    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+    pub fn get_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+        where K: Borrow<Q>, Q: TrieKey
+    {
         let key_fragments = key.encode();
         self.node.get_mut(&key_fragments).and_then(|t| t.value_checked_mut(key))
     }
@@ -31,8 +38,28 @@ impl<K, V> Trie<K, V> where K: TrieKey {
         result
     }
 
+    /// Like `insert`, but reports an allocation failure instead of aborting the process.
+    ///
+    /// The variable-sized fragment storage that node-splitting allocates is reserved
+    /// fallibly, so on `Err` the trie's `length` is unchanged and the trie is otherwise
+    /// untouched. The fixed-size node and key-value shells themselves still go through
+    /// ordinary `Box::new`, since stable Rust has no fallible box allocation; see
+    /// `TrieNode::try_insert` for the node-splitting mechanics and that caveat in detail.
+    /// Intended for embedded / allocation-constrained users; on ordinary desktop and server
+    /// targets `insert` remains the simpler choice.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        let key_fragments = key.encode();
+        let result = self.node.try_insert(key, value, key_fragments)?;
+        if result.is_none() {
+            self.length += 1;
+        }
+        Ok(result)
+    }
+
     /// Remove the value associated with the given key.
-    pub fn remove(&mut self, key: &K) -> Option<V> {
+    pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: TrieKey
+    {
         let removed = self.node.remove(key);
         if removed.is_some() {
             self.length -= 1;
@@ -46,7 +73,9 @@ impl<K, V> Trie<K, V> where K: TrieKey {
     }
 
     /// Fetch a reference to the subtrie for a given key.
-    pub fn subtrie<'a>(&'a self, key: &K) -> Option<SubTrie<'a, K, V>> {
+    pub fn subtrie<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<SubTrie<'a, K, V>>
+        where K: Borrow<Q>, Q: TrieKey
+    {
         let key_fragments = key.encode();
         self.node.get(&key_fragments).map(|node| {
             new_subtrie(key_fragments, node)
@@ -69,7 +98,9 @@ impl<K, V> Trie<K, V> where K: TrieKey {
     /// has a value.
     ///
     /// Invariant: `result.is_some() => result.key_value.is_some()`.
-    pub fn get_ancestor<'a>(&'a self, key: &K) -> Option<SubTrie<'a, K, V>> {
+    pub fn get_ancestor<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<SubTrie<'a, K, V>>
+        where K: Borrow<Q>, Q: TrieKey
+    {
         let key_fragments = key.encode();
         self.node.get_ancestor(&key_fragments).map(|node| {
             new_subtrie(key_fragments, node)
@@ -83,24 +114,88 @@ impl<K, V> Trie<K, V> where K: TrieKey {
         self.get_ancestor(key).and_then(|t| t.node.value())
     }
 
-    // FIXME
-    /*
-    pub fn get_raw_ancestor(&self, key: &K) -> &TrieNode<K, V> {
-        GetRawAncestor::run(self, (), key.encode()).unwrap()
+    /// Fetch every stored value whose key is a prefix of `key`, in root-to-leaf order.
+    ///
+    /// This differs from `get_ancestor` in that it returns the whole chain of matching
+    /// values rather than just the closest one, which is what's needed for longest-prefix
+    /// match routing or dictionary segmentation.
+    pub fn find_prefixes<'a, Q: ?Sized>(&'a self, key: &Q) -> Vec<(&'a K, &'a V)>
+        where K: Borrow<Q>, Q: TrieKey
+    {
+        let mut key_fragments = NibbleVec::from_byte_vec(key.encode());
+        let mut result = vec![];
+        let mut current = &self.node;
+
+        loop {
+            if let Some(ref kv) = current.key_value {
+                result.push((&kv.key, &kv.value));
+            }
+
+            if key_fragments.len() == 0 {
+                break;
+            }
+
+            let bucket = key_fragments.get(0) as usize;
+            match current.children[bucket] {
+                Some(ref child) => {
+                    let common = key_fragments.common_prefix_len(&child.key);
+                    if common != child.key.len() {
+                        break;
+                    }
+                    key_fragments = key_fragments.split(common);
+                    current = child;
+                }
+                None => break,
+            }
+        }
+
+        result
+    }
+
+    /// Fetch the value of the longest stored key that is a prefix of `key`, if any.
+    ///
+    /// This is a shortcut for `find_prefixes(key).pop()`.
+    pub fn find_longest_prefix<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<(&'a K, &'a V)>
+        where K: Borrow<Q>, Q: TrieKey
+    {
+        self.find_prefixes(key).pop()
+    }
+
+    /// Fetch a reference to the raw node reached by matching `key` as far down the trie as
+    /// possible.
+    ///
+    /// Unlike `get_ancestor`, the returned node isn't required to hold a value itself -
+    /// it's simply the deepest node whose accumulated fragment is a prefix of `key`. This
+    /// always succeeds (the root matches at worst), which is why it returns `&TrieNode`
+    /// rather than `Option<SubTrie>`.
+    pub fn get_raw_ancestor<'a, Q: ?Sized>(&'a self, key: &Q) -> &'a TrieNode<K, V>
+        where K: Borrow<Q>, Q: TrieKey
+    {
+        let key_fragments = NibbleVec::from_byte_vec(key.encode());
+        get_raw_ancestor_node(&self.node, key_fragments)
     }
-    */
 
-    /*
     /// Fetch the closest descendant for a given key.
     ///
-    /// If the key is in the trie, this is the same as `get_node`.
-    pub fn get_descendant<'a>(&self, key: &K) -> Option<SubTrie<'a, K, V>> {
-        // FIXME:
-        // let key_fragments = key.encode();
-        // GetDescendant::run(self, (), key_fragments)
-        None
+    /// If the key is in the trie, this is the same as `subtrie`. Otherwise, if `key` runs
+    /// out partway into some node's fragment, the subtrie rooted at that node is returned,
+    /// keyed with the reconstructed prefix leading up to it, so its `Iter` yields every key
+    /// sharing `key` as a prefix.
+    pub fn get_descendant<'a, Q: ?Sized>(&'a self, key: &Q) -> Option<SubTrie<'a, K, V>>
+        where K: Borrow<Q>, Q: TrieKey
+    {
+        let key_fragments = NibbleVec::from_byte_vec(key.encode());
+        match get_raw_descendant(&self.node, key_fragments.clone()) {
+            Some(DescendantResult::ExactMatch(node)) => {
+                Some(new_subtrie(key_fragments, node))
+            }
+            Some(DescendantResult::ExtensionMatch(node, extra_nibbles)) => {
+                let prefix = key_fragments.join(&extra_nibbles);
+                Some(new_subtrie(prefix, node))
+            }
+            None => None,
+        }
     }
-    */
 
     /// Take a function `f` and apply it to the value stored at `key`.
     ///
@@ -124,6 +219,70 @@ impl<K, V> Trie<K, V> where K: TrieKey {
     }
 }
 
+/// Walk `node` down by `key` as far as a matching child exists, returning the deepest node
+/// reached (which may not hold a value itself).
+fn get_raw_ancestor_node<'a, K, V>(node: &'a TrieNode<K, V>, mut key: NibbleVec) -> &'a TrieNode<K, V> {
+    let mut node = node;
+    loop {
+        if key.len() == 0 {
+            return node;
+        }
+
+        let bucket = key.get(0) as usize;
+        match node.children[bucket] {
+            Some(ref child) => {
+                let common = key.common_prefix_len(&child.key);
+                if common != child.key.len() {
+                    return node;
+                }
+                key = key.split(common);
+                node = child;
+            }
+            None => return node,
+        }
+    }
+}
+
+/// The node reached by walking a query key down to its end.
+enum DescendantResult<'a, K: 'a, V: 'a> {
+    /// The query key lines up exactly with the node's accumulated fragment.
+    ExactMatch(&'a TrieNode<K, V>),
+    /// The query key runs out partway into the node's fragment; `extra_nibbles` is the
+    /// remainder of that fragment beyond the query key, needed to reconstruct the subtrie's
+    /// full prefix.
+    ExtensionMatch(&'a TrieNode<K, V>, NibbleVec),
+}
+
+/// Walk `node` down by `key`, returning the node reached once `key` is exhausted.
+fn get_raw_descendant<'a, K, V>(node: &'a TrieNode<K, V>, mut key: NibbleVec)
+    -> Option<DescendantResult<'a, K, V>>
+{
+    if key.len() == 0 {
+        return Some(DescendantResult::ExactMatch(node));
+    }
+
+    let bucket = key.get(0) as usize;
+    match node.children[bucket] {
+        Some(ref child) => {
+            let common = key.common_prefix_len(&child.key);
+            if common == key.len() {
+                if common == child.key.len() {
+                    Some(DescendantResult::ExactMatch(child))
+                } else {
+                    let mut fragment = child.key.clone();
+                    let extra_nibbles = fragment.split(common);
+                    Some(DescendantResult::ExtensionMatch(child, extra_nibbles))
+                }
+            } else if common == child.key.len() {
+                get_raw_descendant(child, key.split(common))
+            } else {
+                None
+            }
+        }
+        None => None,
+    }
+}
+
 // TODO: may as well make these public methods.
 fn new_subtrie<'a, K, V>(prefix: NibbleVec, node: &'a TrieNode<K, V>) -> SubTrie<'a, K, V>
     where K: TrieKey